@@ -4,21 +4,41 @@
 //! This crate provides a faster implementation of `memcpy` for slices up to 32bytes (64bytes with `avx`).
 //! If you know most of you copy operations are not too big you can use `fastcpy` to speed up your program.
 //!
+//! [`slice_copy`] works on any `&[T]` with `T: Copy`, not just `&[u8]` -- the copy happens over
+//! the raw byte span, so small `Copy` structs get the same small-size acceleration.
+//!
 //! `fastcpy` is designed to contain not too much assembly, so the overhead is low.
 //!
 //! As fall back the standard `memcpy` is called
 //!
 //! ## Double Copy Trick
-//! `fastcpy` employs a double copy trick to copy slices of length 4-32bytes (64bytes with `avx`).
+//! `fastcpy` employs a double copy trick to copy slices of length 4-32bytes (64bytes with `avx`,
+//! 128bytes with `avx512f`).
 //! E.g. Slice of length 6 can be copied with two uncoditional copy operations.
 //! ```
 //! /// [1, 2, 3, 4, 5, 6]
 //! /// [1, 2, 3, 4]
 //! ///       [3, 4, 5, 6]
 //! ```
+//!
+//! ## AVX/AVX-512 dispatch
+//! On `std` builds for `x86`/`x86_64`, whether [`slice_copy`]'s 64-byte (`avx`) and 128-byte
+//! (`avx512f`) buckets are used is decided at runtime with `is_x86_feature_detected!`, cached
+//! after the first call, so a single binary still widens its largest bucket on capable CPUs
+//! instead of only doing so when built with `-C target-feature=+avx`. Builds without `std` (or
+//! on other architectures) keep the old compile-time `#[cfg(target_feature = "avx")]` behavior.
+//! [`slice_move`], [`slice_fill`] and [`slice_swap`] always use that compile-time check for
+//! their 64-byte bucket and have no 128-byte bucket.
 
+/// Copies `src` into `dst`, which must have the same length.
+///
+/// `T` only needs to be `Copy`: the copy is done over the raw byte span, so this also
+/// accelerates slices of small `Copy` structs (e.g. `[u32; 2]`, 3-byte RGB pixels, coordinate
+/// pairs), not just `u8`. `T`'s size is a compile-time constant per monomorphization, so the
+/// byte length is a multiplication by a constant; the bucket is then chosen from that byte
+/// length at runtime.
 #[inline]
-pub fn slice_copy(src: &[u8], dst: &mut [u8]) {
+pub fn slice_copy<T: Copy>(src: &[T], dst: &mut [T]) {
     #[inline(never)]
     #[cold]
     #[track_caller]
@@ -32,6 +52,78 @@ pub fn slice_copy(src: &[u8], dst: &mut [u8]) {
     if src.len() != dst.len() {
         len_mismatch_fail(src.len(), dst.len());
     }
+    let byte_len = core::mem::size_of_val(src);
+
+    // SAFETY: `src`/`dst` are valid for `src.len()`/`dst.len()` elements of `T`, so the
+    // corresponding byte spans are valid for `byte_len` bytes each.
+    let src = unsafe { core::slice::from_raw_parts(src.as_ptr() as *const u8, byte_len) };
+    let dst = unsafe { core::slice::from_raw_parts_mut(dst.as_mut_ptr() as *mut u8, byte_len) };
+    copy_bytes(src, dst);
+}
+
+/// Caches which SIMD tier of the bucketed dispatch is safe to use on the current CPU, so the
+/// (relatively expensive) `is_x86_feature_detected!` checks only run once.
+///
+/// This is the `std`-only, x86/x86_64-only runtime counterpart to the `#[cfg(target_feature =
+/// "avx")]` compile-time check used elsewhere. It is only compiled with the `std` feature (builds
+/// without it use that compile-time check instead), and on other architectures it never gets
+/// past [`Tier::Baseline`].
+#[cfg(feature = "std")]
+mod simd_tier {
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    const UNINIT: u8 = 0;
+    const BASELINE: u8 = 1;
+    const AVX: u8 = 2;
+    const AVX512: u8 = 3;
+
+    static CACHED: AtomicU8 = AtomicU8::new(UNINIT);
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub(crate) enum Tier {
+        Baseline,
+        Avx,
+        Avx512,
+    }
+
+    #[inline]
+    pub(crate) fn detect() -> Tier {
+        match CACHED.load(Ordering::Relaxed) {
+            BASELINE => Tier::Baseline,
+            AVX => Tier::Avx,
+            AVX512 => Tier::Avx512,
+            _ => {
+                let tier = detect_uncached();
+                let encoded = match tier {
+                    Tier::Baseline => BASELINE,
+                    Tier::Avx => AVX,
+                    Tier::Avx512 => AVX512,
+                };
+                CACHED.store(encoded, Ordering::Relaxed);
+                tier
+            }
+        }
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    fn detect_uncached() -> Tier {
+        if std::is_x86_feature_detected!("avx512f") {
+            Tier::Avx512
+        } else if std::is_x86_feature_detected!("avx") {
+            Tier::Avx
+        } else {
+            Tier::Baseline
+        }
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    fn detect_uncached() -> Tier {
+        Tier::Baseline
+    }
+}
+
+#[inline]
+fn copy_bytes(src: &[u8], dst: &mut [u8]) {
     let len = src.len();
 
     if src.is_empty() {
@@ -52,7 +144,25 @@ pub fn slice_copy(src: &[u8], dst: &mut [u8]) {
         return;
     }
 
-    /// The code will use the vmovdqu instruction to copy 32 bytes at a time.
+    #[cfg(feature = "std")]
+    {
+        match simd_tier::detect() {
+            simd_tier::Tier::Avx512 | simd_tier::Tier::Avx if len <= 64 => {
+                double_copy_trick::<32>(src, dst);
+                return;
+            }
+            simd_tier::Tier::Avx512 if len <= 128 => {
+                double_copy_trick::<64>(src, dst);
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    // Builds without the `std` feature can't call `is_x86_feature_detected!`, so they fall back
+    // to the old compile-time check: the 64-byte bucket is only used if the whole crate was
+    // built with `-C target-feature=+avx`.
+    #[cfg(not(feature = "std"))]
     #[cfg(target_feature = "avx")]
     {
         if len <= 64 {
@@ -108,9 +218,279 @@ fn double_copy_trick<const SIZE: usize>(src: &[u8], dst: &mut [u8]) {
     }
 }
 
+/// Moves `len` bytes from `src` to `dst`, like `memmove`: unlike [`slice_copy`], `src` and `dst`
+/// are allowed to overlap.
+///
+/// This takes raw pointers rather than slices because a `&[u8]`/`&mut [u8]` pair that alias are
+/// already unsound to construct under Rust's aliasing rules, regardless of how carefully the
+/// function body reads and writes through them.
+///
+/// # Safety
+/// `src` must be valid for reads of `len` bytes and `dst` must be valid for writes of `len`
+/// bytes.
+#[inline]
+pub unsafe fn slice_move(src: *const u8, dst: *mut u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    if len < 8 {
+        short_move(src, dst, len);
+        return;
+    }
+
+    if len <= 16 {
+        double_move_trick::<8>(src, dst, len);
+        return;
+    }
+
+    if len <= 32 {
+        double_move_trick::<16>(src, dst, len);
+        return;
+    }
+
+    #[cfg(target_feature = "avx")]
+    {
+        if len <= 64 {
+            double_move_trick::<32>(src, dst, len);
+            return;
+        }
+    }
+
+    // For larger sizes we fall back to the real memmove, which is overlap-safe.
+    core::ptr::copy(src, dst, len);
+}
+
+#[inline]
+unsafe fn short_move(src: *const u8, dst: *mut u8, len: usize) {
+    if len >= 4 {
+        double_move_trick::<4>(src, dst, len);
+    } else {
+        // length 1-3
+        let first = *src;
+        let last = *src.add(len - 1);
+        let mid = if len == 3 { Some(*src.add(1)) } else { None };
+        *dst = first;
+        *dst.add(len - 1) = last;
+        if let Some(mid) = mid {
+            *dst.add(1) = mid;
+        }
+    }
+}
+
+/// Overlap-safe variant of [`double_copy_trick`]: both chunks are read into local temporaries
+/// before anything is written back, so the snapshot of `src` is taken before `dst` is touched
+/// even when `src` and `dst` alias.
+#[inline]
+unsafe fn double_move_trick<const SIZE: usize>(src: *const u8, dst: *mut u8, len: usize) {
+    let l_begin = src;
+    let l_end = src.add(len - SIZE);
+    let r_begin = dst;
+    let r_end = dst.add(len - SIZE);
+
+    let head: [u8; SIZE] = core::ptr::read_unaligned(l_begin as *const [u8; SIZE]);
+    let tail: [u8; SIZE] = core::ptr::read_unaligned(l_end as *const [u8; SIZE]);
+    core::ptr::write_unaligned(r_begin as *mut [u8; SIZE], head);
+    core::ptr::write_unaligned(r_end as *mut [u8; SIZE], tail);
+}
+
+/// Fills `dst` with `val`, like `memset`, using the same size-bucketed dispatch as
+/// [`slice_copy`], except that the 64-byte bucket is only used when the crate is built with
+/// `-C target-feature=+avx` and there is no 128-byte bucket.
+#[inline]
+pub fn slice_fill(dst: &mut [u8], val: u8) {
+    let len = dst.len();
+
+    if len == 0 {
+        return;
+    }
+    if len < 8 {
+        short_fill(dst, val);
+        return;
+    }
+
+    if len <= 16 {
+        double_fill_trick::<8>(dst, val);
+        return;
+    }
+
+    if len <= 32 {
+        double_fill_trick::<16>(dst, val);
+        return;
+    }
+
+    #[cfg(target_feature = "avx")]
+    {
+        if len <= 64 {
+            double_fill_trick::<32>(dst, val);
+            return;
+        }
+    }
+
+    // For larger sizes we use the default, which calls memset.
+    unsafe {
+        core::ptr::write_bytes(dst.as_mut_ptr(), val, len);
+    }
+}
+
+#[inline]
+fn short_fill(dst: &mut [u8], val: u8) {
+    let len = dst.len();
+
+    if len >= 4 {
+        double_fill_trick::<4>(dst, val);
+    } else {
+        // length 1-3
+        for b in dst.iter_mut() {
+            *b = val;
+        }
+    }
+}
+
+#[inline]
+/// Analogous to [`double_copy_trick`], but both overlapping stores write the same repeated-byte
+/// word, so a 6-byte fill is two 4-byte stores.
+fn double_fill_trick<const SIZE: usize>(dst: &mut [u8], val: u8) {
+    let len = dst.len();
+    let word = [val; SIZE];
+
+    let r_begin = dst.as_mut_ptr();
+    let r_end = unsafe { dst.as_mut_ptr().add(len - SIZE) };
+
+    unsafe {
+        core::ptr::write_unaligned(r_begin as *mut [u8; SIZE], word);
+        core::ptr::write_unaligned(r_end as *mut [u8; SIZE], word);
+    }
+}
+
+/// Swaps the contents of two equal-length, non-overlapping slices, using the same
+/// size-bucketed dispatch as [`slice_copy`], except that the 64-byte bucket is only used when
+/// the crate is built with `-C target-feature=+avx` and there is no 128-byte bucket.
+#[inline]
+pub fn slice_swap(a: &mut [u8], b: &mut [u8]) {
+    #[inline(never)]
+    #[cold]
+    #[track_caller]
+    fn len_mismatch_fail(a_len: usize, b_len: usize) -> ! {
+        panic!(
+            "slice length ({}) does not match other slice length ({})",
+            a_len, b_len,
+        );
+    }
+
+    if a.len() != b.len() {
+        len_mismatch_fail(a.len(), b.len());
+    }
+    let len = a.len();
+
+    if len == 0 {
+        return;
+    }
+    if len < 8 {
+        short_swap(a, b);
+        return;
+    }
+
+    if len <= 16 {
+        double_swap_trick::<8>(a, b);
+        return;
+    }
+
+    if len <= 32 {
+        double_swap_trick::<16>(a, b);
+        return;
+    }
+
+    #[cfg(target_feature = "avx")]
+    {
+        if len <= 64 {
+            double_swap_trick::<32>(a, b);
+            return;
+        }
+    }
+
+    // For larger sizes, hand-rolled SIMD stops paying off; leave vectorization to the backend.
+    for (x, y) in a.iter_mut().zip(b.iter_mut()) {
+        core::mem::swap(x, y);
+    }
+}
+
+#[inline]
+fn short_swap(a: &mut [u8], b: &mut [u8]) {
+    let len = a.len();
+
+    if len >= 4 {
+        double_swap_trick::<4>(a, b);
+    } else {
+        // length 1-3
+        for (x, y) in a.iter_mut().zip(b.iter_mut()) {
+            core::mem::swap(x, y);
+        }
+    }
+}
+
+#[inline]
+/// Analogous to [`double_copy_trick`], but the head/tail chunks of `a` and `b` are loaded into
+/// temporaries before being cross-stored, so a 6-byte swap is two 4-byte exchanges.
+fn double_swap_trick<const SIZE: usize>(a: &mut [u8], b: &mut [u8]) {
+    let len = a.len();
+
+    let a_begin = a.as_mut_ptr();
+    let a_end = unsafe { a.as_mut_ptr().add(len - SIZE) };
+    let b_begin = b.as_mut_ptr();
+    let b_end = unsafe { b.as_mut_ptr().add(len - SIZE) };
+
+    unsafe {
+        let a_head: [u8; SIZE] = core::ptr::read_unaligned(a_begin as *const [u8; SIZE]);
+        let a_tail: [u8; SIZE] = core::ptr::read_unaligned(a_end as *const [u8; SIZE]);
+        let b_head: [u8; SIZE] = core::ptr::read_unaligned(b_begin as *const [u8; SIZE]);
+        let b_tail: [u8; SIZE] = core::ptr::read_unaligned(b_end as *const [u8; SIZE]);
+
+        core::ptr::write_unaligned(a_begin as *mut [u8; SIZE], b_head);
+        core::ptr::write_unaligned(a_end as *mut [u8; SIZE], b_tail);
+        core::ptr::write_unaligned(b_begin as *mut [u8; SIZE], a_head);
+        core::ptr::write_unaligned(b_end as *mut [u8; SIZE], a_tail);
+    }
+}
+
+/// Copies `srcs` contiguously into `dst`, checking once up front that the total source length
+/// matches `dst.len()`, so a mismatch panics before anything is copied.
+///
+/// Useful for serialization/framing code that assembles a buffer from several small fields:
+/// each field goes through the bucketed [`slice_copy`] fast paths. Each field's sub-slice of
+/// `dst` is still taken with a bounds-checked index, and [`slice_copy`]'s own length check
+/// still runs for every field.
+#[inline]
+pub fn copy_from_slices(srcs: &[&[u8]], dst: &mut [u8]) {
+    #[inline(never)]
+    #[cold]
+    #[track_caller]
+    fn len_mismatch_fail(total_len: usize, dst_len: usize) -> ! {
+        panic!(
+            "total source length ({}) does not match destination slice length ({})",
+            total_len, dst_len,
+        );
+    }
+
+    let total_len: usize = srcs.iter().map(|src| src.len()).sum();
+    if total_len != dst.len() {
+        len_mismatch_fail(total_len, dst.len());
+    }
+
+    let mut offset = 0;
+    for src in srcs {
+        let len = src.len();
+        slice_copy(src, &mut dst[offset..offset + len]);
+        offset += len;
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::copy_from_slices;
     use super::slice_copy;
+    use super::slice_fill;
+    use super::slice_move;
+    use super::slice_swap;
     use proptest::prelude::*;
 
     proptest! {
@@ -143,6 +523,147 @@ mod tests {
         assert_eq!(left, right);
     }
 
+    /// Runs `slice_move` from `buf[src_start..][..len]` to `buf[dst_start..][..len]`, returning
+    /// the expected result computed with the slow, obviously-correct `Vec`-based move.
+    fn move_within(buf: &mut [u8], src_start: usize, dst_start: usize, len: usize) -> Vec<u8> {
+        let expected = {
+            let mut v = buf.to_vec();
+            let chunk = buf[src_start..src_start + len].to_vec();
+            v[dst_start..dst_start + len].copy_from_slice(&chunk);
+            v
+        };
+
+        unsafe {
+            let src = buf.as_ptr().add(src_start);
+            let dst = buf.as_mut_ptr().add(dst_start);
+            slice_move(src, dst, len);
+        }
+        expected
+    }
+
+    proptest! {
+        #[test]
+        fn test_slice_move_no_overlap(left: Vec<u8>) {
+            let len = left.len();
+            let mut buf = left.clone();
+            buf.extend(std::iter::repeat_n(0u8, len));
+            let expected = move_within(&mut buf, 0, len, len);
+            prop_assert_eq!(buf, expected);
+        }
+
+        /// `dst` starts after `src` (`dst > src`), so the two ranges overlap at the tail.
+        #[test]
+        fn test_slice_move_overlap_forward(data: Vec<u8>, shift in 0usize..16) {
+            let len = data.len();
+            let mut buf = data;
+            buf.extend(std::iter::repeat_n(0u8, shift));
+            let expected = move_within(&mut buf, 0, shift, len);
+            prop_assert_eq!(buf, expected);
+        }
+
+        /// `dst` starts before `src` (`dst < src`), so the two ranges overlap at the head.
+        #[test]
+        fn test_slice_move_overlap_backward(data: Vec<u8>, shift in 0usize..16) {
+            let len = data.len();
+            let mut buf: Vec<u8> = std::iter::repeat_n(0u8, shift).chain(data).collect();
+            let expected = move_within(&mut buf, shift, 0, len);
+            prop_assert_eq!(buf, expected);
+        }
+    }
+
+    #[test]
+    fn test_slice_move_edge_cases() {
+        for len in 0..(512 * 2) {
+            let mut buf = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+            buf.extend(std::iter::repeat_n(0u8, len));
+            let expected = move_within(&mut buf, 0, len, len);
+            assert_eq!(buf, expected);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_slice_fill(len in 0usize..512, val: u8) {
+            let mut dst = vec![0u8; len];
+            slice_fill(&mut dst, val);
+            prop_assert!(dst.iter().all(|&b| b == val));
+        }
+    }
+
+    #[test]
+    fn test_slice_fill_edge_cases() {
+        for len in 0..(512 * 2) {
+            let mut dst = vec![0u8; len];
+            slice_fill(&mut dst, 0xAB);
+            assert!(dst.iter().all(|&b| b == 0xAB));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_slice_swap(len in 0usize..512, seed: u8) {
+            let left = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+            let right = (0..len).map(|i| (i as u8).wrapping_add(seed)).collect::<Vec<_>>();
+            let mut a = left.clone();
+            let mut b = right.clone();
+            slice_swap(&mut a, &mut b);
+            prop_assert_eq!(a, right);
+            prop_assert_eq!(b, left);
+        }
+    }
+
+    #[test]
+    fn test_slice_swap_edge_cases() {
+        for len in 0..(512 * 2) {
+            let left = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+            let right = (0..len).map(|i| (i as u8).wrapping_mul(7)).collect::<Vec<_>>();
+            let mut a = left.clone();
+            let mut b = right.clone();
+            slice_swap(&mut a, &mut b);
+            assert_eq!(a, right);
+            assert_eq!(b, left);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_copy_from_slices(parts: Vec<Vec<u8>>) {
+            let total_len: usize = parts.iter().map(|p| p.len()).sum();
+            let refs: Vec<&[u8]> = parts.iter().map(|p| p.as_slice()).collect();
+            let mut dst = vec![0u8; total_len];
+            copy_from_slices(&refs, &mut dst);
+            let expected: Vec<u8> = parts.into_iter().flatten().collect();
+            prop_assert_eq!(dst, expected);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_copy_from_slices_len_mismatch() {
+        let a = [1u8, 2, 3];
+        let b = [4u8, 5];
+        let mut dst = vec![0u8; 4];
+        copy_from_slices(&[&a, &b], &mut dst);
+    }
+
+    proptest! {
+        #[test]
+        fn test_slice_copy_generic_u32(left: Vec<u32>) {
+            let mut right = vec![0u32; left.len()];
+            slice_copy(&left, &mut right);
+            prop_assert_eq!(&left, &right);
+        }
+
+        // 3-byte elements are an awkward size where backend auto-vectorization is known to
+        // struggle, which is exactly the case the bucketed fast path should help with.
+        #[test]
+        fn test_slice_copy_generic_3_byte_elements(left: Vec<[u8; 3]>) {
+            let mut right = vec![[0u8; 3]; left.len()];
+            slice_copy(&left, &mut right);
+            prop_assert_eq!(&left, &right);
+        }
+    }
+
     #[test]
     fn test_fail() {
         let left = vec![